@@ -0,0 +1,106 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::Type;
+
+/// The field of the target struct a [`Dependency`] resolves into.
+#[derive(Clone)]
+pub enum TargetField {
+    Named(Ident),
+    Unnamed(usize),
+}
+
+impl TargetField {
+    fn var_ident(&self) -> Ident {
+        match self {
+            TargetField::Named(ident) => ident.clone(),
+            TargetField::Unnamed(index) => Ident::new(&format!("field{}", index), Span::call_site()),
+        }
+    }
+}
+
+/// How a dependency is looked up in the [`Container`].
+#[derive(Clone)]
+pub enum Scope {
+    Singleton,
+    Scoped,
+    /// The field holds a registered provider function (e.g. `Arc<dyn Fn(..) -> T>`),
+    /// looked up by its declared type like a scoped dependency instead of being
+    /// unwrapped and constructed — there's no dedicated container API for it.
+    Factory,
+}
+
+/// A single dependency of an `Injectable` target: a field resolved from the
+/// container and bound to a local variable before the target is constructed.
+pub struct Dependency {
+    field: TargetField,
+    ty: Type,
+    scope: Scope,
+    container: Ident,
+    name: Option<String>,
+}
+
+impl Dependency {
+    pub fn new(
+        field: TargetField,
+        ty: Type,
+        scope: Scope,
+        container: Ident,
+        name: Option<String>,
+    ) -> Self {
+        Dependency {
+            field,
+            ty,
+            scope,
+            container,
+            name,
+        }
+    }
+
+    /// Returns the identifier of the local variable this dependency resolves into.
+    ///
+    /// For named fields this is the field name itself, so it doubles as the
+    /// shorthand used in struct-literal construction (`Type { field }`).
+    pub fn var_name(&self) -> Ident {
+        self.field.var_ident()
+    }
+
+    /// The type this dependency resolves to, after unwrapping `Singleton<T>`/`Arc<..>`;
+    /// a factory field keeps its declared type as-is.
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    /// How this dependency is looked up in the container.
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+}
+
+impl ToTokens for Dependency {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let var = self.var_name();
+        let ty = &self.ty;
+        let container = &self.container;
+
+        let resolved = match (&self.scope, &self.name) {
+            (Scope::Singleton, Some(name)) => {
+                quote! { #container.get_singleton_with_name::<#ty>(#name).unwrap() }
+            }
+            (Scope::Singleton, None) => quote! { #container.get_singleton::<#ty>().unwrap() },
+            // A factory field is registered like any other value (its type is the
+            // provider function itself, e.g. `Arc<dyn Fn(Config) -> Widget>`), so it
+            // is looked up through the same `get`/`get_with_name` accessors as a
+            // scoped dependency — there is no separate factory-only API.
+            (Scope::Scoped, Some(name)) | (Scope::Factory, Some(name)) => {
+                quote! { #container.get_with_name::<#ty>(#name).unwrap() }
+            }
+            (Scope::Scoped, None) | (Scope::Factory, None) => {
+                quote! { #container.get::<#ty>().unwrap() }
+            }
+        };
+
+        tokens.extend(quote! {
+            let #var = #resolved;
+        });
+    }
+}
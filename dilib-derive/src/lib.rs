@@ -0,0 +1,15 @@
+mod dependency;
+mod target;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+#[proc_macro_derive(Injectable, attributes(inject))]
+pub fn derive_injectable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match target::parse_derive_injectable(input) {
+        Ok(target) => target.emit().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
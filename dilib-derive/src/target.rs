@@ -1,13 +1,14 @@
 use crate::dependency::{Dependency, Scope, TargetField};
-use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::{quote, ToTokens};
+use quote::quote;
 use syn::{
-    Data, DataStruct, DeriveInput, Field, Fields, GenericArgument, Ident, PathArguments, Type,
+    parse_quote, Data, DataStruct, DeriveInput, Fields, GenericArgument, Generics, Ident, Lit,
+    Meta, NestedMeta, PathArguments, Result, Type,
 };
 
 pub struct InjectableTarget {
     target_type: Ident,
+    generics: Generics,
     container: Ident,
     constructor: Option<TargetConstructor>,
     deps: Vec<Dependency>,
@@ -16,12 +17,12 @@ pub struct InjectableTarget {
 
 pub struct TargetConstructor {
     name: String,
-    params: Vec<String>,
 }
 
 impl InjectableTarget {
     pub fn new(
         target_type: Ident,
+        generics: Generics,
         container: Ident,
         constructor: Option<TargetConstructor>,
         deps: Vec<Dependency>,
@@ -29,6 +30,7 @@ impl InjectableTarget {
     ) -> Self {
         InjectableTarget {
             target_type,
+            generics,
             container,
             constructor,
             deps,
@@ -43,10 +45,11 @@ impl InjectableTarget {
 
     pub fn emit(&self) -> proc_macro2::TokenStream {
         let target_type = &self.target_type;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
 
         if self.is_unit {
             return quote! {
-                impl dilib::Injectable for #target_type {
+                impl #impl_generics dilib::Injectable for #target_type #ty_generics #where_clause {
                     fn resolve(_: &dilib::Container) -> Self {
                         #target_type
                     }
@@ -58,14 +61,11 @@ impl InjectableTarget {
         let deps = self.deps.as_slice();
 
         let create_instance = if let Some(constructor) = &self.constructor {
-            let params = constructor
-                .params
-                .iter()
-                .map(|s| Ident::new(s, Span::call_site()));
+            let constructor_name = Ident::new(&constructor.name, Span::call_site());
+            let params = deps.iter().map(|s| s.var_name());
 
             // Type :: constructor ( params )
-            let constructor_name = Ident::new(&constructor.name, Span::call_site());
-            quote! { #target_type :: #constructor_name ( #(#params)* )}
+            quote! { #target_type :: #constructor_name ( #(#params),* ) }
         } else {
             let params = deps.iter().map(|s| s.var_name());
 
@@ -74,7 +74,7 @@ impl InjectableTarget {
         };
 
         quote! {
-            impl dilib::Injectable for #target_type {
+            impl #impl_generics dilib::Injectable for #target_type #ty_generics #where_clause {
                 fn resolve(#container : &dilib::Container) -> Self {
                     #(#deps)*
                     #create_instance
@@ -84,25 +84,122 @@ impl InjectableTarget {
     }
 }
 
-pub fn parse_derive_injectable(input: DeriveInput) -> InjectableTarget {
+pub fn parse_derive_injectable(input: DeriveInput) -> Result<InjectableTarget> {
     match &input.data {
-        Data::Enum(_) => panic!("Enum types cannot implement `Injectable` with #[derive]"),
-        Data::Union(_) => panic!("Union types cannot implement `Injectable` with #[derive]"),
+        Data::Enum(data_enum) => Err(syn::Error::new(
+            data_enum.enum_token.span,
+            "Enum types cannot implement `Injectable` with #[derive]",
+        )),
+        Data::Union(data_union) => Err(syn::Error::new(
+            data_union.union_token.span,
+            "Union types cannot implement `Injectable` with #[derive]",
+        )),
         Data::Struct(data_struct) => {
             let target_type = input.ident.clone();
-            let constructor = get_target_constructor(&input);
+            let constructor = get_target_constructor(&input)?;
             let container = get_container_identifier(data_struct);
-            let deps = get_deps(&data_struct.fields);
+            let deps = get_deps(&data_struct.fields)?;
+            let generics = get_bounded_generics(&input.generics, &deps);
             let is_unit = data_struct.fields == Fields::Unit;
 
-            InjectableTarget::new(target_type, container, constructor, deps, is_unit)
+            Ok(InjectableTarget::new(
+                target_type,
+                generics,
+                container,
+                constructor,
+                deps,
+                is_unit,
+            ))
+        }
+    }
+}
+
+/// Bounds each of the target's type parameters on what resolving its dependencies
+/// actually needs, so the derived `impl` only requires what it uses instead of a
+/// blanket `Injectable` bound that would reject e.g. `struct Repo<T> { conn: Singleton<T> }`
+/// (`T` there is never constructed through `Injectable` — it's read out of the
+/// container as a `Singleton<T>` payload, which only needs `Send + Sync + 'static`).
+fn get_bounded_generics(generics: &Generics, deps: &[Dependency]) -> Generics {
+    let mut generics = generics.clone();
+
+    for dep in deps {
+        let ident = match bare_type_param(dep.ty(), &generics) {
+            Some(ident) => ident,
+            None => continue,
+        };
+
+        let bounds: Vec<syn::TypeParamBound> = match dep.scope() {
+            // Constructed through the container, so it must itself be `Injectable`.
+            Scope::Scoped => vec![parse_quote!(dilib::Injectable), parse_quote!('static)],
+            // Read out of the container as a shared value, so it must be thread-safe
+            // and own no borrowed data.
+            Scope::Singleton => vec![
+                parse_quote!(Send),
+                parse_quote!(Sync),
+                parse_quote!('static),
+            ],
+            // Pulled out of the container as-is; only needs to outlive the container.
+            Scope::Factory => vec![parse_quote!('static)],
+        };
+
+        for param in generics.type_params_mut() {
+            if param.ident == ident {
+                param.bounds.extend(bounds.clone());
+            }
         }
     }
+
+    generics
+}
+
+/// If `ty` is a bare reference to one of `generics`' type parameters (e.g. the `T`
+/// in `Singleton<T>` after unwrapping, not some unrelated concrete type), returns
+/// its identifier.
+fn bare_type_param(ty: &Type, generics: &Generics) -> Option<Ident> {
+    let type_path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        _ => return None,
+    };
+
+    let ident = type_path.path.get_ident()?;
+    generics
+        .type_params()
+        .find(|param| &param.ident == ident)
+        .map(|param| param.ident.clone())
 }
 
-fn get_target_constructor(_input: &DeriveInput) -> Option<TargetConstructor> {
-    // todo
-    None
+/// Looks for a `#[inject(constructor = "method_name")]` attribute on the target type
+/// and, if present, returns the `TargetConstructor` that should be used to build it
+/// instead of struct-literal initialization.
+fn get_target_constructor(input: &DeriveInput) -> Result<Option<TargetConstructor>> {
+    for attr in &input.attrs {
+        if !attr.path.is_ident("inject") {
+            continue;
+        }
+
+        let meta_list = match attr.parse_meta()? {
+            Meta::List(meta_list) => meta_list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `inject(..)`")),
+        };
+
+        for nested in meta_list.nested {
+            if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                if name_value.path.is_ident("constructor") {
+                    return match &name_value.lit {
+                        Lit::Str(lit_str) => Ok(Some(TargetConstructor {
+                            name: lit_str.value(),
+                        })),
+                        lit => Err(syn::Error::new_spanned(
+                            lit,
+                            "`constructor` must be a string literal naming an associated function",
+                        )),
+                    };
+                }
+            }
+        }
+    }
+
+    Ok(None)
 }
 
 fn get_container_identifier(struct_data: &DataStruct) -> Ident {
@@ -114,7 +211,8 @@ fn get_container_identifier(struct_data: &DataStruct) -> Ident {
             let mut matches = 1_usize;
 
             for f in &fields.named {
-                let field_name = f.ident.as_ref().unwrap().to_string();
+                // SAFETY: fields of `Fields::Named` always have an identifier.
+                let field_name = f.ident.as_ref().expect("named field without an ident").to_string();
 
                 // We prevent name collisions
                 // If there is a field named `container` we try other name
@@ -132,106 +230,334 @@ fn get_container_identifier(struct_data: &DataStruct) -> Ident {
     }
 }
 
-fn get_deps(fields: &Fields) -> Vec<Dependency> {
+fn get_deps(fields: &Fields) -> Result<Vec<Dependency>> {
     let mut deps = Vec::new();
     let container = Ident::new("container", Span::call_site());
 
-    // todo: check for attributes for additional config
-
     match fields {
-        Fields::Unit => deps,
+        Fields::Unit => Ok(deps),
         Fields::Named(fields_named) => {
             for f in &fields_named.named {
-                let field = TargetField::Named(f.ident.clone().unwrap());
-                let (field_type, scope) = get_type_and_scope(&f.ty);
-                let dependency = Dependency::new(
-                    field,
-                    field_type,
-                    scope,
-                    container.clone()
+                let field = TargetField::Named(
+                    f.ident.clone().expect("named field without an ident"),
                 );
+                let (field_type, scope) = get_field_type_and_scope(f)?;
+                let name = get_dependency_name(&f.attrs)?;
+                let dependency =
+                    Dependency::new(field, field_type, scope, container.clone(), name);
 
                 deps.push(dependency);
             }
 
-            deps
+            Ok(deps)
         }
         Fields::Unnamed(fields_unnamed) => {
             for (index, f) in fields_unnamed.unnamed.iter().enumerate() {
                 let field = TargetField::Unnamed(index);
-                let (field_type, scope) = get_type_and_scope(&f.ty);
-                let dependency = Dependency::new(
-                    field,
-                    field_type,
-                    scope,
-                    container.clone()
-                );
+                let (field_type, scope) = get_field_type_and_scope(f)?;
+                let name = get_dependency_name(&f.attrs)?;
+                let dependency =
+                    Dependency::new(field, field_type, scope, container.clone(), name);
 
                 deps.push(dependency);
             }
 
-            deps
+            Ok(deps)
         }
     }
 }
 
-fn get_type_and_scope(ty: &Type) -> (Type, Scope) {
-    if let Some(generic) = get_singleton_type(ty) {
-        (generic, Scope::Singleton)
+/// Resolves a field's dependency type and [`Scope`], honoring `#[inject(factory)]`:
+/// a factory field is pulled out of the container as-is (its provider type), rather
+/// than being unwrapped for `Singleton`/`Arc` construction.
+fn get_field_type_and_scope(field: &syn::Field) -> Result<(Type, Scope)> {
+    if has_factory_attr(&field.attrs)? {
+        Ok((field.ty.clone(), Scope::Factory))
     } else {
-        (ty.clone(), Scope::Scoped)
+        get_type_and_scope(&field.ty)
     }
 }
 
-fn get_singleton_type(ty: &Type) -> Option<Type> {
-    match ty {
-        Type::Path(type_path) => {
-            // Is declared as <T as Trait>::Inner
-            if type_path.qself.is_some() {
-                return None;
+/// Looks for a `#[inject(factory)]` attribute on a field, marking it as a
+/// registered provider function rather than a directly constructed value.
+fn has_factory_attr(attrs: &[syn::Attribute]) -> Result<bool> {
+    for attr in attrs {
+        if !attr.path.is_ident("inject") {
+            continue;
+        }
+
+        let meta_list = match attr.parse_meta()? {
+            Meta::List(meta_list) => meta_list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `inject(..)`")),
+        };
+
+        for nested in meta_list.nested {
+            if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                if path.is_ident("factory") {
+                    return Ok(true);
+                }
             }
+        }
+    }
 
-            // todo: We are not checking full paths like: dilib::Singleton<T>
+    Ok(false)
+}
 
-            let raw = type_path.path.to_token_stream().to_string();
-            let s = raw.split_ascii_whitespace().collect::<String>();
+/// Looks for a `#[inject(name = "...")]` attribute on a field, used to disambiguate
+/// between several bindings of the same type (e.g. a primary vs. a replica database).
+fn get_dependency_name(attrs: &[syn::Attribute]) -> Result<Option<String>> {
+    for attr in attrs {
+        if !attr.path.is_ident("inject") {
+            continue;
+        }
 
-            // SAFETY: A type path should have at least 1 element
-            let segment = type_path.path.segments.last().unwrap();
-            let ident = segment.ident.to_string();
+        let meta_list = match attr.parse_meta()? {
+            Meta::List(meta_list) => meta_list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `inject(..)`")),
+        };
 
-            // Is `Singleton<T>`
-            if ident == "Singleton" && !segment.arguments.is_empty() {
-                match &segment.arguments {
-                    PathArguments::AngleBracketed(bracketed) => {
-                        let generic_arg = bracketed.args.first().unwrap();
-                        if let GenericArgument::Type(Type::Path(generic_type)) = generic_arg {
-                            return Some(Type::Path(generic_type.clone()));
-                        }
-                    }
-                    _ => {}
+        for nested in meta_list.nested {
+            if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                if name_value.path.is_ident("name") {
+                    return match &name_value.lit {
+                        Lit::Str(lit_str) => Ok(Some(lit_str.value())),
+                        lit => Err(syn::Error::new_spanned(
+                            lit,
+                            "`name` must be a string literal",
+                        )),
+                    };
                 }
             }
+        }
+    }
+
+    Ok(None)
+}
+
+fn get_type_and_scope(ty: &Type) -> Result<(Type, Scope)> {
+    if let Some(generic) = get_singleton_type(ty)? {
+        Ok((generic, Scope::Singleton))
+    } else {
+        Ok((ty.clone(), Scope::Scoped))
+    }
+}
+
+fn get_singleton_type(ty: &Type) -> Result<Option<Type>> {
+    let type_path = match ty {
+        // Types declared as <T as Trait>::Inner are never singletons
+        Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        _ => return Ok(None),
+    };
+
+    // `path.segments.last()` looks past any module prefix, so this already matches
+    // fully-qualified paths like `dilib::Singleton<T>` or `std::sync::Arc<T>`,
+    // not just the names a glob-imported caller would write.
+    let segment = match type_path.path.segments.last() {
+        Some(segment) => segment,
+        None => return Ok(None),
+    };
+    let ident = segment.ident.to_string();
+
+    // Is `Singleton<T>`
+    if ident == "Singleton" {
+        return match first_generic_arg(segment)? {
+            Some(arg) => Ok(Some(as_type(arg)?)),
+            None => Ok(None),
+        };
+    }
 
-            // Is `Arc<Mutex<T>>`
-            if ident == "Arc" {
-                match &segment.arguments {
-                    PathArguments::AngleBracketed(bracket) => {
-                        let generic_arg = bracket.args.first().unwrap();
-                        if let GenericArgument::Type(Type::Path(generic)) = generic_arg {
-                            let inner = generic.path.segments.last().unwrap();
-                            if inner.ident.to_string() == "Mutex" {
-                                return Some(Type::Path(generic.clone()));
-                            }
-                        }
+    // Is `Arc<..>`: `Arc<Mutex<T>>`/`Arc<RwLock<T>>` are interior-mutable singletons,
+    // a bare `Arc<T>` is a shared immutable singleton, and `Arc<dyn Trait>` is a
+    // singleton trait object.
+    if ident == "Arc" {
+        let inner = match first_generic_arg(segment)? {
+            Some(GenericArgument::Type(inner)) => inner,
+            _ => return Ok(None),
+        };
+
+        if let Type::Path(inner_path) = &inner {
+            if inner_path.qself.is_none() {
+                if let Some(inner_segment) = inner_path.path.segments.last() {
+                    if inner_segment.ident == "Mutex" || inner_segment.ident == "RwLock" {
+                        // The singleton key is `Mutex<T>`/`RwLock<T>` itself, not the `T`
+                        // it wraps: `Singleton<Mutex<T>>` = `Arc<Mutex<T>>`, matching the
+                        // field's declared type.
+                        return Ok(Some(inner));
                     }
-                    _ => {}
                 }
             }
+        }
+
+        // `Arc<T>` or `Arc<dyn Trait>`: the wrapped type is the singleton itself.
+        return Ok(Some(inner));
+    }
+
+    Ok(None)
+}
+
+/// Returns the first generic argument of a path segment like `Foo<Bar>`, if any.
+fn first_generic_arg(segment: &syn::PathSegment) -> Result<Option<GenericArgument>> {
+    match &segment.arguments {
+        PathArguments::AngleBracketed(bracketed) => Ok(bracketed.args.first().cloned()),
+        _ => Ok(None),
+    }
+}
+
+fn as_type(arg: GenericArgument) -> Result<Type> {
+    match arg {
+        GenericArgument::Type(ty) => Ok(ty),
+        other => Err(syn::Error::new_spanned(
+            other,
+            "expected a concrete type argument",
+        )),
+    }
+}
 
-            None
+// This crate has no `Cargo.toml` in this tree, so a `trybuild` macro-expansion
+// harness can't be wired up here. These tests instead exercise the parsing and
+// codegen helpers directly against `syn` types, which needs no container crate
+// to compile.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::ToTokens;
+
+    fn first_field_attrs(src: &str) -> Vec<syn::Attribute> {
+        let input: DeriveInput = syn::parse_str(src).unwrap();
+        match input.data {
+            Data::Struct(data_struct) => match data_struct.fields {
+                Fields::Named(fields) => fields.named[0].attrs.clone(),
+                Fields::Unnamed(fields) => fields.unnamed[0].attrs.clone(),
+                Fields::Unit => vec![],
+            },
+            _ => panic!("expected a struct"),
         }
-        _ => None,
+    }
+
+    #[test]
+    fn parses_constructor_attribute() {
+        let input: DeriveInput =
+            syn::parse_str(r#"#[inject(constructor = "new")] struct Foo { a: i32 }"#).unwrap();
+
+        let constructor = get_target_constructor(&input).unwrap().unwrap();
+        assert_eq!(constructor.name, "new");
+    }
+
+    #[test]
+    fn no_constructor_attribute_is_none() {
+        let input: DeriveInput = syn::parse_str("struct Foo { a: i32 }").unwrap();
+        assert!(get_target_constructor(&input).unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_dependency_name_attribute() {
+        let attrs = first_field_attrs(r#"struct Foo { #[inject(name = "sqlite")] a: i32 }"#);
+        assert_eq!(get_dependency_name(&attrs).unwrap().as_deref(), Some("sqlite"));
+    }
+
+    #[test]
+    fn detects_factory_attribute() {
+        let attrs = first_field_attrs("struct Foo { #[inject(factory)] a: i32 }");
+        assert!(has_factory_attr(&attrs).unwrap());
+
+        let attrs = first_field_attrs("struct Foo { a: i32 }");
+        assert!(!has_factory_attr(&attrs).unwrap());
+    }
+
+    fn singleton_type(src: &str) -> Option<Type> {
+        let ty: Type = syn::parse_str(src).unwrap();
+        get_singleton_type(&ty).unwrap()
+    }
+
+    #[test]
+    fn singleton_wrapper_unwraps_to_inner() {
+        assert_eq!(
+            singleton_type("Singleton<Config>").unwrap().to_token_stream().to_string(),
+            "Config"
+        );
+    }
+
+    #[test]
+    fn arc_mutex_resolves_to_the_mutex_not_the_inner_t() {
+        // Regression test: the singleton key for `Arc<Mutex<T>>` is `Mutex<T>`
+        // (`Singleton<Mutex<T>>` = `Arc<Mutex<T>>`), not the bare `T` one level down.
+        assert_eq!(
+            singleton_type("Arc<Mutex<Config>>").unwrap().to_token_stream().to_string(),
+            "Mutex < Config >"
+        );
+    }
+
+    #[test]
+    fn arc_rwlock_resolves_to_the_rwlock_not_the_inner_t() {
+        assert_eq!(
+            singleton_type("Arc<RwLock<Config>>").unwrap().to_token_stream().to_string(),
+            "RwLock < Config >"
+        );
+    }
+
+    #[test]
+    fn bare_arc_resolves_to_the_wrapped_type() {
+        assert_eq!(
+            singleton_type("Arc<Config>").unwrap().to_token_stream().to_string(),
+            "Config"
+        );
+    }
+
+    #[test]
+    fn arc_dyn_trait_resolves_to_the_trait_object() {
+        assert_eq!(
+            singleton_type("Arc<dyn Weapon>").unwrap().to_token_stream().to_string(),
+            "dyn Weapon"
+        );
+    }
+
+    #[test]
+    fn plain_type_is_not_a_singleton() {
+        assert!(singleton_type("Config").is_none());
+    }
+
+    #[test]
+    fn bounded_generics_do_not_require_injectable_for_singleton_params() {
+        // `struct Repo<T> { conn: Singleton<T> }`: `T` is read out of the container
+        // as a `Singleton<T>` payload, never constructed through `Injectable`.
+        let generics: Generics = parse_quote!(<T>);
+        let container = Ident::new("container", Span::call_site());
+        let deps = vec![Dependency::new(
+            TargetField::Named(Ident::new("conn", Span::call_site())),
+            parse_quote!(T),
+            Scope::Singleton,
+            container,
+            None,
+        )];
+
+        let bounded = get_bounded_generics(&generics, &deps);
+        let param = bounded.type_params().next().unwrap();
+        let bounds = param.bounds.to_token_stream().to_string();
+
+        assert!(!bounds.contains("Injectable"));
+        assert!(bounds.contains("Send"));
+        assert!(bounds.contains("Sync"));
+    }
+
+    #[test]
+    fn bounded_generics_require_injectable_for_scoped_params() {
+        // `struct Service<T> { repo: T }`: `T` is constructed directly through the
+        // container, so it must itself be `Injectable`.
+        let generics: Generics = parse_quote!(<T>);
+        let container = Ident::new("container", Span::call_site());
+        let deps = vec![Dependency::new(
+            TargetField::Named(Ident::new("repo", Span::call_site())),
+            parse_quote!(T),
+            Scope::Scoped,
+            container,
+            None,
+        )];
+
+        let bounded = get_bounded_generics(&generics, &deps);
+        let param = bounded.type_params().next().unwrap();
+        let bounds = param.bounds.to_token_stream().to_string();
+
+        assert!(bounds.contains("Injectable"));
     }
 }
 